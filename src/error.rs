@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while tagging or exporting assets.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("missing Immich API Key (set the IMMICH_API_KEY environment variable)")]
+    MissingApiKey,
+
+    #[error("API key must be ASCII")]
+    InvalidApiKey(#[source] reqwest::header::InvalidHeaderValue),
+
+    #[error("could not build HTTP client")]
+    BuildClient(#[source] reqwest::Error),
+
+    #[error("could not open gpx file {path}")]
+    OpenGpxFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("could not parse gpx data in {path}")]
+    ParseGpx {
+        path: PathBuf,
+        #[source]
+        source: gpx::errors::GpxError,
+    },
+
+    #[error("failed to format gpx time as string")]
+    FormatGpxTime(#[source] gpx::errors::GpxError),
+
+    #[error("failed to parse gpx time")]
+    ParseGpxTime(#[source] chrono::ParseError),
+
+    #[error("failed to convert capture time into gpx time")]
+    ConvertCaptureTime(#[source] time::error::Parse),
+
+    #[error("EXIF capture time is ambiguous or invalid in timezone {tz}")]
+    AmbiguousLocalTime { tz: chrono_tz::Tz },
+
+    #[error("invalid time offset: {0}")]
+    InvalidTimeOffset(String),
+
+    #[error("request to Immich failed")]
+    Request(#[source] reqwest::Error),
+
+    #[error("Immich API returned {status}: {body}")]
+    Api {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    #[error("could not open output file {path}")]
+    OpenOutputFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("could not write gpx data")]
+    WriteGpx(#[source] gpx::errors::GpxError),
+}
@@ -0,0 +1,80 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Utc};
+use gpx::{Gpx, GpxVersion, Track, TrackSegment, Waypoint};
+
+use crate::{
+    SearchArgs,
+    error::{Error, Result},
+    immich,
+};
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    #[clap(flatten)]
+    search: SearchArgs,
+
+    /// Where to write the GPX output. Defaults to stdout.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub async fn run(args: ExportArgs) -> Result<()> {
+    let api_key = dotenv::var("IMMICH_API_KEY").map_err(|_| Error::MissingApiKey)?;
+    let client = immich::build_client(&api_key)?;
+    let base_url = format!("{}/api", args.search.server);
+
+    let mut assets = immich::search_all_assets(&client, &base_url, &args.search)
+        .await?
+        .into_iter()
+        .filter(|img| {
+            args.search.owner.as_ref().is_none_or(|id| id == &img.owner_id)
+                && img.exif_info.latitude.is_some()
+                && img.exif_info.longitude.is_some()
+        })
+        .collect::<Vec<_>>();
+    assets.sort_unstable_by_key(|img| img.exif_info.date_time_original);
+
+    eprintln!("exporting {} geotagged asset(s)", assets.len());
+
+    let mut segment = TrackSegment::default();
+    for asset in &assets {
+        let mut waypoint = Waypoint::new(geo_types::Point::new(
+            asset.exif_info.longitude.unwrap(),
+            asset.exif_info.latitude.unwrap(),
+        ));
+        waypoint.time = Some(to_gpx_time(asset.exif_info.date_time_original)?);
+        segment.points.push(waypoint);
+    }
+
+    let mut track = Track::default();
+    track.segments.push(segment);
+
+    let mut gpx = Gpx::default();
+    gpx.version = GpxVersion::Gpx11;
+    gpx.tracks.push(track);
+
+    match args.output {
+        Some(path) => gpx::write(
+            &gpx,
+            BufWriter::new(File::create(&path).map_err(|source| Error::OpenOutputFile {
+                path,
+                source,
+            })?),
+        )
+        .map_err(Error::WriteGpx)?,
+        None => gpx::write(&gpx, io::stdout().lock()).map_err(Error::WriteGpx)?,
+    }
+
+    Ok(())
+}
+
+fn to_gpx_time(dt: DateTime<Utc>) -> Result<gpx::Time> {
+    time::OffsetDateTime::parse(&dt.to_rfc3339(), &time::format_description::well_known::Rfc3339)
+        .map(gpx::Time::from)
+        .map_err(Error::ConvertCaptureTime)
+}
@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use reqwest::{
+    Client, Response,
+    header::{HeaderName, HeaderValue},
+};
+use serde_json::json;
+
+use crate::{
+    SearchArgs,
+    error::{Error, Result},
+};
+
+/// Build an HTTP client authenticated against the Immich API with the given
+/// API key.
+pub fn build_client(api_key: &str) -> Result<Client> {
+    Client::builder()
+        .default_headers(
+            [(
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_str(api_key).map_err(Error::InvalidApiKey)?,
+            )]
+            .into_iter()
+            .collect(),
+        )
+        .build()
+        .map_err(Error::BuildClient)
+}
+
+/// Fetch every asset matching `args`, transparently following pages until
+/// Immich returns a page with fewer items than the page size.
+pub async fn search_all_assets(
+    client: &Client,
+    base_url: &str,
+    args: &SearchArgs,
+) -> Result<Vec<AssetResponseDto>> {
+    let mut assets = Vec::new();
+    let mut page = args.page;
+    let mut page_size = None;
+    let mut pages_fetched = 0;
+    loop {
+        if args.max_pages.is_some_and(|max| page - args.page >= max) {
+            break;
+        }
+
+        let response = client
+            .post(format!("{base_url}/search/metadata"))
+            .json(&json!({
+                "page": page,
+                "withExif": true,
+                "country": null,
+                "make": args.camera_brand,
+                "model": args.camera_model,
+            }))
+            .send()
+            .await
+            .map_err(Error::Request)?;
+        let mut page_items = ensure_success(response)
+            .await?
+            .json::<SearchResult>()
+            .await
+            .map_err(Error::Request)?
+            .assets
+            .items;
+        pages_fetched += 1;
+
+        let page_len = page_items.len();
+        let page_size = *page_size.get_or_insert(page_len);
+        assets.append(&mut page_items);
+
+        if page_len < page_size || page_len == 0 {
+            break;
+        }
+        page += 1;
+    }
+    eprintln!(
+        "scanned {} assets across {} page(s)",
+        assets.len(),
+        pages_fetched
+    );
+
+    Ok(assets)
+}
+
+/// Set an asset's location via `PUT /assets/{id}`.
+pub async fn update_asset_location(
+    client: &Client,
+    base_url: &str,
+    id: &str,
+    latitude: f64,
+    longitude: f64,
+) -> Result<()> {
+    let response = client
+        .put(format!("{base_url}/assets/{id}"))
+        .json(&json!({
+            "latitude": latitude,
+            "longitude": longitude,
+        }))
+        .send()
+        .await
+        .map_err(Error::Request)?;
+    ensure_success(response).await?;
+    Ok(())
+}
+
+async fn ensure_success(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Api { status, body })
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct SearchResult {
+    assets: SearchAssetResponseDto,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct SearchAssetResponseDto {
+    items: Vec<AssetResponseDto>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetResponseDto {
+    pub id: String,
+    pub exif_info: ExifResponseDto,
+    pub owner_id: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExifResponseDto {
+    pub date_time_original: DateTime<Utc>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
@@ -0,0 +1,334 @@
+use std::{fs::File, io::BufReader, path::PathBuf, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use itertools::Itertools;
+use tokio::sync::Semaphore;
+
+use crate::{
+    SearchArgs,
+    error::{Error, Result},
+    immich,
+};
+
+#[derive(clap::Args)]
+pub struct TagArgs {
+    /// Paths to gpx input files.
+    gpx_files: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    search: SearchArgs,
+
+    /// Don't actually send updates to Immich.
+    #[clap(short = 'n', long)]
+    dry_run: bool,
+
+    /// Maximum number of asset updates to have in flight at once.
+    #[clap(long, default_value = "8")]
+    concurrency: usize,
+
+    /// Skip tagging an image when the two bracketing GPX points are further
+    /// apart in time than this many seconds.
+    #[clap(long)]
+    max_gap_seconds: Option<i64>,
+
+    /// Offset applied to each asset's capture time before matching it
+    /// against the GPX track, to correct for a camera clock that wasn't
+    /// synced to GPS time. E.g. `+2h`, `-00:45:00`, or a plain number of
+    /// seconds.
+    #[clap(long)]
+    time_offset: Option<TimeOffset>,
+
+    /// Interpret capture times as local times in this IANA timezone (e.g.
+    /// `Europe/Berlin`) instead of UTC. This applies to every asset's capture
+    /// time, whether or not its EXIF data carried timezone info.
+    #[clap(long)]
+    assume_tz: Option<chrono_tz::Tz>,
+}
+
+/// A signed duration parsed from a CLI argument, accepting `+2h`,
+/// `-00:45:00`, or a plain number of seconds.
+#[derive(Debug, Clone, Copy)]
+struct TimeOffset(chrono::Duration);
+
+impl std::str::FromStr for TimeOffset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidTimeOffset(s.to_owned());
+
+        let (negative, rest) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        let seconds: i64 = if let Some(parts) = rest.strip_suffix('h') {
+            parts.parse::<i64>().map_err(|_| invalid())? * 3600
+        } else if let Some(parts) = rest.strip_suffix('m') {
+            parts.parse::<i64>().map_err(|_| invalid())? * 60
+        } else if let Some(parts) = rest.strip_suffix('s') {
+            parts.parse().map_err(|_| invalid())?
+        } else if rest.contains(':') {
+            let mut components = rest.splitn(3, ':');
+            let hours: i64 = components.next().unwrap().parse().map_err(|_| invalid())?;
+            let minutes: i64 = components
+                .next()
+                .map(|m| m.parse())
+                .transpose()
+                .map_err(|_| invalid())?
+                .unwrap_or(0);
+            let seconds: i64 = components
+                .next()
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|_| invalid())?
+                .unwrap_or(0);
+            hours * 3600 + minutes * 60 + seconds
+        } else {
+            rest.parse().map_err(|_| invalid())?
+        };
+
+        Ok(Self(chrono::Duration::seconds(if negative {
+            -seconds
+        } else {
+            seconds
+        })))
+    }
+}
+
+pub async fn run(args: TagArgs) -> Result<()> {
+    let api_key = dotenv::var("IMMICH_API_KEY").map_err(|_| Error::MissingApiKey)?;
+
+    let mut location_data = args
+        .gpx_files
+        .iter()
+        .map(|path| {
+            Result::Ok(
+                gpx::read(BufReader::new(File::open(path).map_err(|source| {
+                    Error::OpenGpxFile {
+                        path: path.clone(),
+                        source,
+                    }
+                })?))
+                .map_err(|source| Error::ParseGpx {
+                    path: path.clone(),
+                    source,
+                })?
+                .tracks
+                .into_iter()
+                .flat_map(|track| track.segments)
+                .map(|segment| {
+                    segment
+                        .points
+                        .into_iter()
+                        .filter_map(|p| Some(convert_time(p.time?).map(|t| (t, p.point().x_y()))))
+                        .collect::<Result<Vec<_>>>()
+                }),
+            )
+        })
+        .flatten_ok()
+        .flatten_ok()
+        .collect::<Result<Vec<_>>>()?;
+
+    for segment in &mut location_data {
+        segment.sort_unstable_by_key(|p| p.0);
+    }
+
+    let client = immich::build_client(&api_key)?;
+    let base_url = format!("{}/api", args.search.server);
+
+    let assets = immich::search_all_assets(&client, &base_url, &args.search).await?;
+
+    let semaphore = Arc::new(Semaphore::new(args.concurrency));
+    let mut tasks = Vec::new();
+    for image in assets.into_iter().filter(|img| {
+        args.search.owner.as_ref().is_none_or(|id| id == &img.owner_id)
+            && img.exif_info.latitude.is_none()
+            && img.exif_info.longitude.is_none()
+    }) {
+        // adjust for the camera clock's timezone and any known drift
+        let mut capture_time = image.exif_info.date_time_original;
+        if let Some(tz) = args.assume_tz {
+            capture_time = capture_time
+                .naive_utc()
+                .and_local_timezone(tz)
+                .single()
+                .ok_or(Error::AmbiguousLocalTime { tz })?
+                .to_utc();
+        }
+        if let Some(offset) = args.time_offset {
+            capture_time += offset.0;
+        }
+
+        // find track including this time, if any
+        let Some(track) = location_data
+            .iter()
+            .filter(|track| !track.is_empty())
+            .find(|track| {
+                capture_time >= track.first().unwrap().0 && capture_time <= track.last().unwrap().0
+            })
+        else {
+            continue;
+        };
+
+        // find the positions bracketing the capture time
+        let next = track.partition_point(|p| p.0 <= capture_time);
+        let a = &track[next - 1];
+        // in case the last point is exactly at when the image was taken
+        let b = track.get(next).unwrap_or(a);
+
+        // skip over gaps between distinct tracks' adjacent points
+        if args
+            .max_gap_seconds
+            .is_some_and(|max_gap| (b.0 - a.0).num_seconds().abs() > max_gap)
+        {
+            continue;
+        }
+
+        // interpolate position based on capture time
+        let points_dt = (b.0 - a.0).num_seconds().max(1) as f64;
+        let capture_dt = (capture_time - a.0).num_seconds().max(1) as f64;
+        let (longitude, latitude) = slerp(a.1, b.1, capture_dt / points_dt);
+
+        // set location info
+        println!(
+            "setting location {latitude}, {longitude} for image {}/photos/{}",
+            args.search.server, image.id,
+        );
+        if args.dry_run {
+            continue;
+        }
+
+        let client = client.clone();
+        let base_url = base_url.clone();
+        let semaphore = Arc::clone(&semaphore);
+        tasks.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            let result =
+                immich::update_asset_location(&client, &base_url, &image.id, latitude, longitude)
+                    .await;
+            (image.id, result)
+        }));
+    }
+
+    let mut successes = 0;
+    let mut failures = 0;
+    for task in join_all(tasks).await {
+        match task {
+            Ok((_, Ok(()))) => successes += 1,
+            Ok((id, Err(err))) => {
+                failures += 1;
+                eprintln!("failed to update asset {id}: {err}");
+            }
+            Err(err) => {
+                failures += 1;
+                eprintln!("update task panicked: {err}");
+            }
+        }
+    }
+    println!("updated {successes} asset(s), {failures} failure(s)");
+
+    Ok(())
+}
+
+fn convert_time(time: gpx::Time) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(
+        &time.format().map_err(Error::FormatGpxTime)?,
+    )
+    .map_err(Error::ParseGpxTime)?
+    .to_utc())
+}
+
+/// Spherically interpolate between `a` and `b` (longitude, latitude in
+/// degrees), a fraction `f` of the way from `a` to `b`. Falls back to linear
+/// interpolation when the two points are (near) identical or (near)
+/// antipodal, where `sin(d)` vanishes and the great-circle path is undefined
+/// or ambiguous.
+fn slerp(a: (f64, f64), b: (f64, f64), f: f64) -> (f64, f64) {
+    let (lon1, lat1) = (a.0.to_radians(), a.1.to_radians());
+    let (lon2, lat2) = (b.0.to_radians(), b.1.to_radians());
+
+    let d = 2.0
+        * (((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2))
+        .sqrt()
+        .asin();
+
+    if d.sin().abs() < 1e-12 {
+        return (a.0 + (b.0 - a.0) * f, a.1 + (b.1 - a.1) * f);
+    }
+
+    let coef_a = ((1.0 - f) * d).sin() / d.sin();
+    let coef_b = (f * d).sin() / d.sin();
+    let x = coef_a * lat1.cos() * lon1.cos() + coef_b * lat2.cos() * lon2.cos();
+    let y = coef_a * lat1.cos() * lon1.sin() + coef_b * lat2.cos() * lon2.sin();
+    let z = coef_a * lat1.sin() + coef_b * lat2.sin();
+
+    (y.atan2(x).to_degrees(), z.atan2((x * x + y * y).sqrt()).to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point_approx_eq(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn slerp_endpoints_are_exact() {
+        let a = (-10.0, 20.0);
+        let b = (30.0, -5.0);
+        assert_point_approx_eq(slerp(a, b, 0.0), a);
+        assert_point_approx_eq(slerp(a, b, 1.0), b);
+    }
+
+    #[test]
+    fn slerp_midpoint_on_the_equator() {
+        // Two points on the equator, 90 degrees of longitude apart; the
+        // great-circle midpoint between them is also on the equator.
+        let a = (0.0, 0.0);
+        let b = (90.0, 0.0);
+        assert_point_approx_eq(slerp(a, b, 0.5), (45.0, 0.0));
+    }
+
+    #[test]
+    fn slerp_falls_back_to_linear_for_identical_points() {
+        let a = (12.5, -3.5);
+        assert_point_approx_eq(slerp(a, a, 0.5), a);
+    }
+
+    #[test]
+    fn slerp_falls_back_to_linear_for_antipodal_points() {
+        let a = (0.0, 0.0);
+        let b = (180.0, 0.0);
+        let (lon, lat) = slerp(a, b, 0.5);
+        assert!(lon.is_finite() && lat.is_finite());
+    }
+
+    #[test]
+    fn time_offset_parses_hours() {
+        let offset: TimeOffset = "+2h".parse().unwrap();
+        assert_eq!(offset.0, chrono::Duration::hours(2));
+    }
+
+    #[test]
+    fn time_offset_parses_negative_clock_format() {
+        let offset: TimeOffset = "-00:45:00".parse().unwrap();
+        assert_eq!(offset.0, chrono::Duration::minutes(-45));
+    }
+
+    #[test]
+    fn time_offset_parses_bare_seconds() {
+        let offset: TimeOffset = "90".parse().unwrap();
+        assert_eq!(offset.0, chrono::Duration::seconds(90));
+    }
+
+    #[test]
+    fn time_offset_rejects_invalid_input() {
+        assert!("not-a-duration".parse::<TimeOffset>().is_err());
+    }
+}